@@ -14,14 +14,17 @@
 
 //! The fundamental druid types.
 
+use std::any::Any;
+use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
 use std::time::Instant;
 
 use log;
 
 use crate::bloom::Bloom;
-use crate::kurbo::{Affine, Rect, Shape, Size};
+use crate::kurbo::{Affine, Point, Rect, Shape, Size, Vec2};
 use crate::piet::{Piet, RenderContext};
 use crate::{
     BoxConstraints, Command, Cursor, Data, Env, Event, LifeCycle, Target, Text, TimerToken, Widget,
@@ -34,6 +37,187 @@ pub type BoxedWidget<T> = WidgetPod<T, Box<dyn Widget<T>>>;
 /// Our queue type
 pub(crate) type CommandQueue = VecDeque<(Target, Command)>;
 
+/// A deferred structural mutation queued via [`EventCtx::mutate_later`],
+/// waiting to be delivered to the widget it targets.
+///
+/// [`EventCtx::mutate_later`]: struct.EventCtx.html#method.mutate_later
+pub(crate) type MutateCallback = Box<dyn FnOnce(&mut dyn Any, &mut BaseState)>;
+
+/// Queue of pending deferred mutations, keyed by the `WidgetId` of the
+/// widget each callback should be run against.
+pub(crate) type MutateQueue = VecDeque<(WidgetId, MutateCallback)>;
+
+/// A thread-safe handle for posting commands back into a window's event
+/// loop from outside it.
+///
+/// Obtained via [`EventCtx::run_in_background`], which hands a clone of the
+/// window's sink to a closure running on a background thread. Submitting to
+/// the sink doesn't run anything immediately; it just appends to a queue
+/// that the root drains into the regular [`CommandQueue`] once per cycle,
+/// same as [`EventCtx::submit_command`].
+///
+/// [`EventCtx::run_in_background`]: struct.EventCtx.html#method.run_in_background
+/// [`EventCtx::submit_command`]: struct.EventCtx.html#method.submit_command
+#[derive(Clone)]
+pub struct ExtEventSink {
+    window_id: WindowId,
+    queue: std::sync::Arc<std::sync::Mutex<CommandQueue>>,
+}
+
+impl ExtEventSink {
+    pub(crate) fn new(window_id: WindowId) -> ExtEventSink {
+        ExtEventSink {
+            window_id,
+            queue: std::sync::Arc::new(std::sync::Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Submit a command, to be delivered the next time the event loop drains
+    /// this sink.
+    ///
+    /// May be called from any thread. If `target` is `None`, the command is
+    /// sent to this sink's window.
+    pub fn submit_command(&self, command: impl Into<Command>, target: impl Into<Option<Target>>) {
+        let target = target.into().unwrap_or_else(|| self.window_id.into());
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.push_back((target, command.into()));
+        }
+    }
+
+    /// Remove and return all commands submitted since the last drain.
+    ///
+    /// Called by the root once per event-loop cycle, before commands are
+    /// dispatched, so background results are woven into the normal
+    /// `CommandQueue` processing.
+    pub(crate) fn drain(&self) -> CommandQueue {
+        match self.queue.lock() {
+            Ok(mut queue) => std::mem::take(&mut *queue),
+            Err(_) => VecDeque::new(),
+        }
+    }
+}
+
+/// Which pass recorded a [`DebugEntry`].
+///
+/// [`DebugEntry`]: struct.DebugEntry.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugPass {
+    Event,
+    LifeCycle,
+    Layout,
+    Update,
+    Paint,
+}
+
+/// One context call a widget made during a [`DebugPass`], as recorded by a
+/// [`DebugLogger`].
+///
+/// [`DebugPass`]: enum.DebugPass.html
+/// [`DebugLogger`]: struct.DebugLogger.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugAction {
+    Invalidate,
+    RequestAnimFrame,
+    SetActive(bool),
+    RequestFocus,
+    SetHandled,
+    SubmitCommand,
+    ChildrenChanged,
+    /// A widget's [`layout`](trait.Widget.html#tymethod.layout) ran; the
+    /// resulting `Size` is recorded in [`DebugEntry::size`](struct.DebugEntry.html#structfield.size).
+    Layout,
+    /// A widget's [`paint`](trait.Widget.html#tymethod.paint) ran.
+    Paint,
+}
+
+/// A single recorded [`DebugAction`], tagged with the widget and pass it
+/// happened in, and the widget's size at the time, if known.
+///
+/// [`DebugAction`]: enum.DebugAction.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugEntry {
+    pub widget_id: WidgetId,
+    pub pass: DebugPass,
+    pub action: DebugAction,
+    pub size: Option<Size>,
+}
+
+/// An opt-in recorder of per-widget, per-pass context activity.
+///
+/// A handle can be cloned freely and threaded through [`EventCtx`],
+/// [`LifeCycleCtx`], [`LayoutCtx`], [`UpdateCtx`], and [`PaintCtx`]; all
+/// clones share the same underlying log, so entries recorded from anywhere
+/// in the widget tree during a pass land in one place. Logging is disabled
+/// by default, so leaving the hooks compiled in costs nothing until
+/// [`set_enabled`] is called.
+///
+/// This exists to make event-propagation and focus bugs -- which were
+/// previously opaque, since the flags these contexts manipulate are all
+/// `pub(crate)` -- debuggable: tooling can call [`entries`] to see exactly
+/// which widget did what, in which pass, to explain why a frame repainted
+/// or which widget ended up handling an event.
+///
+/// [`EventCtx`]: struct.EventCtx.html
+/// [`LifeCycleCtx`]: struct.LifeCycleCtx.html
+/// [`LayoutCtx`]: struct.LayoutCtx.html
+/// [`UpdateCtx`]: struct.UpdateCtx.html
+/// [`set_enabled`]: #method.set_enabled
+/// [`entries`]: #method.entries
+#[derive(Clone, Default)]
+pub struct DebugLogger(Rc<RefCell<DebugLoggerInner>>);
+
+#[derive(Default)]
+struct DebugLoggerInner {
+    enabled: bool,
+    entries: Vec<DebugEntry>,
+}
+
+impl DebugLogger {
+    /// Create a new, disabled logger with an empty log.
+    pub fn new() -> DebugLogger {
+        DebugLogger::default()
+    }
+
+    /// Turn recording on or off.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.0.borrow_mut().enabled = enabled;
+    }
+
+    /// Returns `true` if this logger is currently recording.
+    pub fn is_enabled(&self) -> bool {
+        self.0.borrow().enabled
+    }
+
+    /// The captured entries, in the order they were recorded.
+    pub fn entries(&self) -> Vec<DebugEntry> {
+        self.0.borrow().entries.clone()
+    }
+
+    /// Discard all captured entries, without changing whether logging is
+    /// enabled.
+    pub fn clear(&self) {
+        self.0.borrow_mut().entries.clear();
+    }
+
+    pub(crate) fn record(
+        &self,
+        widget_id: WidgetId,
+        pass: DebugPass,
+        action: DebugAction,
+        size: Option<Size>,
+    ) {
+        let mut inner = self.0.borrow_mut();
+        if inner.enabled {
+            inner.entries.push(DebugEntry {
+                widget_id,
+                pass,
+                action,
+                size,
+            });
+        }
+    }
+}
+
 /// A container for one widget in the hierarchy.
 ///
 /// Generally, container widgets don't contain other widgets directly,
@@ -74,16 +258,27 @@ pub(crate) struct BaseState {
 
     // TODO: consider using bitflags for the booleans.
 
-    // This should become an invalidation rect.
-    pub(crate) needs_inval: bool,
+    /// The sub-areas of this widget (in its own coordinate space) that have
+    /// changed since the last paint, accumulated from this widget's own
+    /// invalidation requests and merged up from its children.
+    pub(crate) needs_inval: Region,
 
     is_hot: bool,
 
     is_active: bool,
 
+    /// This widget has been disabled via `EventCtx::set_disabled`.
+    ///
+    /// A disabled widget (and its whole subtree) is excluded from mouse and
+    /// keyboard dispatch and from focus.
+    pub(crate) is_disabled: bool,
+
     /// Any descendant is active.
     has_active: bool,
 
+    /// This widget or any descendant is focused.
+    has_focus: bool,
+
     /// Any descendant has requested an animation frame.
     pub(crate) request_anim: bool,
 
@@ -96,10 +291,18 @@ pub(crate) struct BaseState {
     pub(crate) request_focus: Option<FocusChange>,
     pub(crate) children: Bloom<WidgetId>,
     pub(crate) children_changed: bool,
+
+    /// The ordered tab chain of focusable descendants, collected during
+    /// `LifeCycle::Register` in depth-first, child-insertion order.
+    ///
+    /// This is rebuilt from scratch whenever `children_changed` causes a
+    /// fresh `Register` pass, and is what `FocusChange::Next`/`Previous`
+    /// are resolved against.
+    pub(crate) focus_chain: Vec<WidgetId>,
 }
 
 /// Methods by which a widget can attempt to change focus state.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum FocusChange {
     /// The focused widget is giving up focus.
     Resign,
@@ -137,6 +340,16 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         self.state.has_active
     }
 
+    /// Returns `true` if this widget or any descendant is focused.
+    pub fn has_focus(&self) -> bool {
+        self.state.has_focus
+    }
+
+    /// Returns `true` if this widget has been disabled.
+    pub fn is_disabled(&self) -> bool {
+        self.state.is_disabled
+    }
+
     /// Query the "hot" state of the widget.
     pub fn is_hot(&self) -> bool {
         self.state.is_hot
@@ -172,6 +385,22 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         self.state.layout_rect
     }
 
+    /// Determine whether `pos`, in the coordinate space of this widget's
+    /// parent, falls within this widget.
+    ///
+    /// The default behavior, used by most widgets, tests the winding number
+    /// of `pos` against [`get_layout_rect`]. A widget holding many children
+    /// in a way that makes a per-child linear scan expensive (for example a
+    /// canvas backed by a quadtree or grid) can override [`Widget::hit_test`]
+    /// to answer the query directly, without containers needing to know
+    /// anything changed.
+    ///
+    /// [`get_layout_rect`]: #method.get_layout_rect
+    /// [`Widget::hit_test`]: trait.Widget.html#method.hit_test
+    pub fn hit_test(&self, pos: Point) -> bool {
+        self.inner.hit_test(pos, self.state.layout_rect)
+    }
+
     /// Paint a child widget.
     ///
     /// Generally called by container widgets as part of their [`paint`]
@@ -190,8 +419,15 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             region: paint_ctx.region.clone(),
             base_state: &self.state,
             focus_widget: paint_ctx.focus_widget,
+            debug_logger: paint_ctx.debug_logger.clone(),
         };
         self.inner.paint(&mut ctx, data, &env);
+        ctx.debug_logger.record(
+            self.id(),
+            DebugPass::Paint,
+            DebugAction::Paint,
+            Some(self.state.size()),
+        );
     }
 
     /// Paint the widget, translating it by the origin of its layout rectangle.
@@ -251,7 +487,14 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         data: &T,
         env: &Env,
     ) -> Size {
-        self.inner.layout(layout_ctx, bc, data, &env)
+        let size = self.inner.layout(layout_ctx, bc, data, &env);
+        layout_ctx.debug_logger.record(
+            self.id(),
+            DebugPass::Layout,
+            DebugAction::Layout,
+            Some(size),
+        );
+        size
     }
 
     /// Propagate an event.
@@ -270,7 +513,9 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             let mut lc_ctx = ctx.make_lifecycle_ctx();
             self.inner
                 .lifecycle(&mut lc_ctx, &LifeCycle::WidgetAdded, data, &env);
-            self.state.needs_inval |= lc_ctx.needs_inval;
+            if lc_ctx.needs_inval {
+                self.state.needs_inval.add_whole_widget(self.state.size());
+            }
             self.old_data = Some(data.clone());
             self.env = Some(env.clone());
         }
@@ -287,6 +532,8 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             win_ctx: ctx.win_ctx,
             cursor: ctx.cursor,
             command_queue: ctx.command_queue,
+            mutate_queue: ctx.mutate_queue,
+            ext_event_sink: ctx.ext_event_sink.clone(),
             window: &ctx.window,
             window_id: ctx.window_id,
             base_state: &mut self.state,
@@ -294,8 +541,17 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             is_handled: false,
             is_root: false,
             focus_widget: ctx.focus_widget,
+            is_disabled: ctx.is_disabled || ctx.base_state.is_disabled,
+            debug_logger: ctx.debug_logger.clone(),
         };
         let rect = child_ctx.base_state.layout_rect;
+        // A disabled widget (and everything below it) takes no part in
+        // mouse or keyboard dispatch: it can't become hot or active, and
+        // can't hold or receive focus. This is about whether *this* child
+        // itself is disabled, same as the gate its own parent already
+        // applied one level up -- distinct from `EventCtx::is_disabled`,
+        // which widgets query to ask "is my ancestor chain disabled".
+        let is_disabled = child_ctx.base_state.is_disabled;
         // Note: could also represent this as `Option<Event>`.
         let mut recurse = true;
         let mut hot_changed = None;
@@ -306,51 +562,52 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             }
             Event::MouseDown(mouse_event) => {
                 let had_hot = child_ctx.base_state.is_hot;
-                let now_hot = rect.winding(mouse_event.pos) != 0;
+                let now_hot = !is_disabled && self.hit_test(mouse_event.pos);
                 if (!had_hot) && now_hot {
                     child_ctx.base_state.is_hot = true;
                     hot_changed = Some(true);
                 }
-                recurse = had_active || !ctx.had_active && now_hot;
+                recurse = !is_disabled && (had_active || !ctx.had_active && now_hot);
                 let mut mouse_event = mouse_event.clone();
                 mouse_event.pos -= rect.origin().to_vec2();
                 Event::MouseDown(mouse_event)
             }
             Event::MouseUp(mouse_event) => {
-                recurse = had_active || !ctx.had_active && rect.winding(mouse_event.pos) != 0;
+                recurse =
+                    !is_disabled && (had_active || !ctx.had_active && self.hit_test(mouse_event.pos));
                 let mut mouse_event = mouse_event.clone();
                 mouse_event.pos -= rect.origin().to_vec2();
                 Event::MouseUp(mouse_event)
             }
             Event::MouseMoved(mouse_event) => {
                 let had_hot = child_ctx.base_state.is_hot;
-                child_ctx.base_state.is_hot = rect.winding(mouse_event.pos) != 0;
+                child_ctx.base_state.is_hot = !is_disabled && self.hit_test(mouse_event.pos);
                 if had_hot != child_ctx.base_state.is_hot {
                     hot_changed = Some(child_ctx.base_state.is_hot);
                 }
-                recurse = had_active || had_hot || child_ctx.base_state.is_hot;
+                recurse = !is_disabled && (had_active || had_hot || child_ctx.base_state.is_hot);
                 let mut mouse_event = mouse_event.clone();
                 mouse_event.pos -= rect.origin().to_vec2();
                 Event::MouseMoved(mouse_event)
             }
             Event::KeyDown(e) => {
-                recurse = child_ctx.has_focus();
+                recurse = !is_disabled && child_ctx.has_focus();
                 Event::KeyDown(*e)
             }
             Event::KeyUp(e) => {
-                recurse = child_ctx.has_focus();
+                recurse = !is_disabled && child_ctx.has_focus();
                 Event::KeyUp(*e)
             }
             Event::Paste(e) => {
-                recurse = child_ctx.has_focus();
+                recurse = !is_disabled && child_ctx.has_focus();
                 Event::Paste(e.clone())
             }
             Event::Wheel(wheel_event) => {
-                recurse = had_active || child_ctx.base_state.is_hot;
+                recurse = !is_disabled && (had_active || child_ctx.base_state.is_hot);
                 Event::Wheel(wheel_event.clone())
             }
             Event::Zoom(zoom) => {
-                recurse = had_active || child_ctx.base_state.is_hot;
+                recurse = !is_disabled && (had_active || child_ctx.base_state.is_hot);
                 Event::Zoom(*zoom)
             }
             Event::Timer(id) => {
@@ -367,13 +624,15 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 }
             },
         };
-        child_ctx.base_state.needs_inval = false;
+        child_ctx.base_state.needs_inval = Region::EMPTY;
         if let Some(is_hot) = hot_changed {
             let hot_changed_event = LifeCycle::HotChanged(is_hot);
             let mut lc_ctx = child_ctx.make_lifecycle_ctx();
             self.inner
                 .lifecycle(&mut lc_ctx, &hot_changed_event, data, &env);
-            ctx.base_state.needs_inval |= lc_ctx.needs_inval;
+            if lc_ctx.needs_inval {
+                ctx.base_state.needs_inval.add_rect(rect);
+            }
         }
         if recurse {
             child_ctx.base_state.has_active = false;
@@ -381,16 +640,35 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             child_ctx.base_state.has_active |= child_ctx.base_state.is_active;
         };
 
+        if child_ctx.base_state.is_disabled != is_disabled {
+            let disabled_changed_event = LifeCycle::DisabledChanged(child_ctx.base_state.is_disabled);
+            let mut lc_ctx = child_ctx.make_lifecycle_ctx();
+            self.inner
+                .lifecycle(&mut lc_ctx, &disabled_changed_event, data, &env);
+            if lc_ctx.needs_inval {
+                child_ctx.base_state.needs_inval.add_whole_widget(rect.size());
+            }
+        }
+
         ctx.base_state.merge_up(&child_ctx.base_state);
         ctx.is_handled |= child_ctx.is_handled;
     }
 
     pub fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
         ctx.widget_id = self.id();
+        // `is_disabled` is a downward-propagated ancestor flag, not an
+        // accumulator like `children`/`needs_inval` below: a widget is
+        // effectively disabled if it or any ancestor is disabled, so OR in
+        // our own flag rather than overwriting what ancestors set, and
+        // restore the ancestor's value once we're done recursing so later
+        // siblings see it unaffected by our own `is_disabled`.
+        let pre_is_disabled = ctx.is_disabled;
+        ctx.is_disabled = pre_is_disabled || self.state.is_disabled;
         let pre_children = ctx.children;
         let pre_childs_changed = ctx.children_changed;
         let pre_inval = ctx.needs_inval;
         let pre_request_anim = ctx.request_anim;
+        let pre_focus_widgets = std::mem::take(&mut ctx.focus_widgets);
 
         ctx.children = Bloom::new();
         ctx.children_changed = false;
@@ -417,20 +695,51 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             LifeCycle::HotChanged(_) => false,
             LifeCycle::RouteFocusChanged { old, new } => {
                 self.state.request_focus = None;
-                let this_changed = old.map(|_| false).or_else(|| new.map(|_| true));
+                // `FocusChanged` is delivered only to the leaf that is
+                // actually gaining or losing focus, so this must compare
+                // against our own id, not merely check that `old`/`new` are
+                // present.
+                let this_changed = if *new == Some(self.id()) {
+                    Some(true)
+                } else if *old == Some(self.id()) {
+                    Some(false)
+                } else {
+                    None
+                };
                 if let Some(change) = this_changed {
                     let event = LifeCycle::FocusChanged(change);
                     self.inner.lifecycle(ctx, &event, data, env);
+                    self.state.has_focus = change;
                     false
                 } else {
-                    old.map(|id| ctx.children.contains(&id)).unwrap_or(false)
-                        || new.map(|id| ctx.children.contains(&id)).unwrap_or(false)
+                    let had_child_focus = self.state.has_focus;
+                    // Use the already-built `self.state.children`, not
+                    // `ctx.children`: during this pass `ctx.children` is the
+                    // fresh accumulator for the *current* call (only
+                    // populated by `LifeCycle::Register`), so it would
+                    // always report an empty subtree here.
+                    let old_in_subtree = old
+                        .map(|id| self.state.children.contains(&id))
+                        .unwrap_or(false);
+                    let has_child_focus = new
+                        .map(|id| self.state.children.contains(&id))
+                        .unwrap_or(false);
+                    if had_child_focus != has_child_focus {
+                        let event = LifeCycle::ChildFocusChanged(has_child_focus);
+                        self.inner.lifecycle(ctx, &event, data, env);
+                    }
+                    self.state.has_focus = has_child_focus;
+                    // Recurse if either end of the transition is below us, so
+                    // the leaf losing focus is reached as well.
+                    old_in_subtree || has_child_focus
                 }
             }
             LifeCycle::FocusChanged(_) => {
                 self.state.request_focus = None;
                 true
             }
+            LifeCycle::ChildFocusChanged(_) => true,
+            LifeCycle::DisabledChanged(_) => true,
             _ => true,
         };
 
@@ -443,13 +752,19 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         ctx.request_anim |= pre_request_anim;
         ctx.children_changed |= pre_childs_changed;
         ctx.needs_inval |= pre_inval;
+        ctx.is_disabled = pre_is_disabled;
 
         // we only want to update child state after this specific event.
         if let LifeCycle::Register = event {
             self.state.children = ctx.children;
             self.state.children_changed = false;
+            self.state.focus_chain = std::mem::take(&mut ctx.focus_widgets);
             ctx.children = ctx.children.union(pre_children);
+            ctx.focus_widgets = pre_focus_widgets;
+            ctx.focus_widgets.extend(self.state.focus_chain.iter().copied());
             ctx.register_child(self.id());
+        } else {
+            ctx.focus_widgets = pre_focus_widgets;
         }
     }
 
@@ -460,12 +775,21 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
     ///
     /// [`update`]: trait.Widget.html#method.update
     pub fn update(&mut self, ctx: &mut UpdateCtx, data: &T, env: &Env) {
+        // See the matching comment in `lifecycle` above: OR in our own flag
+        // rather than overwriting the ancestor value `ctx` already carries,
+        // and restore it once we're done so siblings aren't affected.
+        let pre_is_disabled = ctx.is_disabled;
+        ctx.is_disabled = pre_is_disabled || self.state.is_disabled;
         match (self.old_data.as_ref(), self.env.as_ref()) {
-            (Some(d), Some(e)) if d.same(data) && e.same(env) => return,
+            (Some(d), Some(e)) if d.same(data) && e.same(env) => {
+                ctx.is_disabled = pre_is_disabled;
+                return;
+            }
             (None, _) => {
                 log::warn!("old_data missing in {:?}, skipping update", self.id());
                 self.old_data = Some(data.clone());
                 self.env = Some(env.clone());
+                ctx.is_disabled = pre_is_disabled;
                 return;
             }
             _ => (),
@@ -484,6 +808,7 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         self.state.children_changed |= ctx.children_changed;
         ctx.children_changed |= pre_childs_changed;
         ctx.needs_inval |= pre_inval;
+        ctx.is_disabled = pre_is_disabled;
     }
 }
 
@@ -495,6 +820,39 @@ impl<T: Data, W: Widget<T> + 'static> WidgetPod<T, W> {
     pub fn boxed(self) -> BoxedWidget<T> {
         WidgetPod::new(Box::new(self.inner))
     }
+
+    /// Deliver a queued [`EventCtx::mutate_later`] callback to the widget it
+    /// targets.
+    ///
+    /// If this is the targeted widget, the callback is run immediately with
+    /// type-erased mutable access to the inner widget and its `BaseState`.
+    /// Otherwise, if `id` might be further down this subtree, the callback is
+    /// forwarded to [`Widget::mutate`], which containers implement to recurse
+    /// into their own children the same way they recurse for
+    /// `Event::TargetedCommand`.
+    ///
+    /// Returns `true` if the callback was delivered, so that a parent calling
+    /// this can mark itself dirty without needing direct access to the
+    /// descendant's `BaseState`.
+    ///
+    /// [`EventCtx::mutate_later`]: struct.EventCtx.html#method.mutate_later
+    /// [`Widget::mutate`]: trait.Widget.html#method.mutate
+    pub(crate) fn mutate(&mut self, id: WidgetId, callback: MutateCallback) -> bool {
+        if self.id() == id {
+            // `Widget::as_any_mut` reaches the concrete widget, rather than
+            // just coercing `&mut W` to `&mut dyn Any`: for `W = Box<dyn
+            // Widget<T>>` (i.e. `BoxedWidget<T>`), the latter would make the
+            // box itself the `Any` type, so `downcast_mut` in `mutate_later`
+            // could never reach the widget the caller actually asked for.
+            callback(self.inner.as_any_mut(), &mut self.state);
+            return true;
+        }
+        if self.state.children.contains(&id) && self.inner.mutate(id, callback) {
+            self.state.needs_inval.add_whole_widget(self.state.size());
+            return true;
+        }
+        false
+    }
 }
 
 impl BaseState {
@@ -502,25 +860,31 @@ impl BaseState {
         BaseState {
             id,
             layout_rect: Rect::ZERO,
-            needs_inval: false,
+            needs_inval: Region::EMPTY,
             is_hot: false,
             is_active: false,
+            is_disabled: false,
             has_active: false,
             request_anim: false,
             request_timer: false,
+            has_focus: false,
             request_focus: None,
             children: Bloom::new(),
             children_changed: false,
+            focus_chain: Vec::new(),
         }
     }
 
     /// Update to incorporate state changes from a child.
     fn merge_up(&mut self, child_state: &BaseState) {
-        self.needs_inval |= child_state.needs_inval;
+        let offset = child_state.layout_rect.origin().to_vec2();
+        self.needs_inval
+            .merge_translated(&child_state.needs_inval, offset);
         self.request_anim |= child_state.request_anim;
         self.request_timer |= child_state.request_timer;
         self.is_hot |= child_state.is_hot;
         self.has_active |= child_state.has_active;
+        self.has_focus |= child_state.has_focus;
         self.children_changed |= child_state.children_changed;
         self.request_focus = self.request_focus.or(child_state.request_focus);
     }
@@ -529,6 +893,72 @@ impl BaseState {
     fn size(&self) -> Size {
         self.layout_rect.size()
     }
+
+    /// Resolve a `FocusChange::Next`/`FocusChange::Previous` request against
+    /// this widget's `focus_chain`, returning the id that should receive
+    /// focus next.
+    ///
+    /// `current` is the id of the widget that currently holds focus, or
+    /// `None` if no widget is focused. Stepping off either end of the chain
+    /// wraps around. Returns `None` if there are no focusable widgets at
+    /// all.
+    ///
+    /// Only meaningful on the `BaseState` of the root widget, since that is
+    /// the only one whose `focus_chain` spans the whole tree.
+    pub(crate) fn resolve_focus_change(
+        &self,
+        current: Option<WidgetId>,
+        change: FocusChange,
+    ) -> Option<WidgetId> {
+        if self.focus_chain.is_empty() {
+            return None;
+        }
+        let step: isize = match change {
+            FocusChange::Next => 1,
+            FocusChange::Previous => -1,
+            _ => return None,
+        };
+        let len = self.focus_chain.len() as isize;
+        let cur_idx = current.and_then(|id| self.focus_chain.iter().position(|&w| w == id));
+        let next_idx = match cur_idx {
+            Some(idx) => (idx as isize + step).rem_euclid(len),
+            // No widget currently focused: `Next` starts at the first entry,
+            // `Previous` starts at the last.
+            None if change == FocusChange::Previous => len - 1,
+            None => 0,
+        };
+        Some(self.focus_chain[next_idx as usize])
+    }
+
+    /// Resolve a pending focus request into the concrete `old`/`new` pair to
+    /// route as `LifeCycle::RouteFocusChanged`.
+    ///
+    /// This is the focus-update pass: it should be run once per cycle, on
+    /// the root `BaseState`, after event handling has finished and before
+    /// `update`, so that any `request_focus`/`focus_next`/`focus_prev`/
+    /// `resign_focus` call made while handling the event is turned into a
+    /// single, deterministic focus transition. `current` is the id of the
+    /// widget that currently holds focus, if any.
+    ///
+    /// `Next`/`Previous` are resolved against `focus_chain`, which only
+    /// contains widgets that were enabled when they registered via
+    /// `LifeCycleCtx::register_for_focus` during the last `Register` pass;
+    /// an empty chain (or a request that doesn't resolve to anyone, which
+    /// can't currently happen but is handled defensively) is treated the
+    /// same as `Resign`. Returns `None`, without touching focus, if there
+    /// was no pending request.
+    pub(crate) fn resolve_pending_focus(
+        &mut self,
+        current: Option<WidgetId>,
+    ) -> Option<(Option<WidgetId>, Option<WidgetId>)> {
+        let change = self.request_focus.take()?;
+        let new = match change {
+            FocusChange::Resign => None,
+            FocusChange::Focus(id) => Some(id),
+            FocusChange::Next | FocusChange::Previous => self.resolve_focus_change(current, change),
+        };
+        Some((current, new))
+    }
 }
 
 /// A context passed to paint methods of widgets.
@@ -546,28 +976,114 @@ pub struct PaintCtx<'a, 'b: 'a> {
     pub(crate) region: Region,
     pub(crate) base_state: &'a BaseState,
     pub(crate) focus_widget: Option<WidgetId>,
+    /// Handle used to record context activity for the [`DebugLogger`]
+    /// subsystem; a no-op unless logging has been enabled.
+    ///
+    /// [`DebugLogger`]: struct.DebugLogger.html
+    pub(crate) debug_logger: DebugLogger,
 }
 
 /// A region of a widget, generally used to describe what needs to be drawn.
+///
+/// A `Region` is a set of disjoint rectangles. Unlike a single bounding
+/// `Rect`, it lets damage tracking stay precise when only a few small areas
+/// of a large widget tree actually changed, so paint can skip subtrees that
+/// don't overlap any of them.
 #[derive(Debug, Clone)]
-pub struct Region(Rect);
+pub struct Region(Vec<Rect>);
 
 impl Region {
+    /// The empty region, which intersects nothing.
+    pub const EMPTY: Region = Region(Vec::new());
+
     /// Returns the smallest `Rect` that encloses the entire region.
+    ///
+    /// Kept for callers (such as [`PaintCtx::region`] consumers) that only
+    /// need a single bounding box rather than the precise set of rects.
     pub fn to_rect(&self) -> Rect {
-        self.0
+        let mut rects = self.0.iter();
+        match rects.next() {
+            Some(first) => rects.fold(*first, |acc, rect| acc.union(*rect)),
+            None => Rect::ZERO,
+        }
     }
 
     /// Returns `true` if `self` intersects with `other`.
     #[inline]
     pub fn intersects(&self, other: Rect) -> bool {
-        self.0.intersect(other).area() > 0.
+        self.0.iter().any(|rect| rect.intersect(other).area() > 0.)
+    }
+
+    /// Returns `true` if this region contains no area.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds `rect` to the region.
+    ///
+    /// If `rect` overlaps an existing rectangle in the region, the two are
+    /// coalesced into their union rather than kept as separate entries, and
+    /// that union goes on absorbing any further rectangle it overlaps, so the
+    /// region's rectangles stay pairwise disjoint; this keeps the region
+    /// small without needing a true disjoint-rectangle decomposition.
+    pub fn add_rect(&mut self, rect: Rect) {
+        if rect.area() <= 0. {
+            return;
+        }
+        let mut rect = rect;
+        while let Some(idx) = self
+            .0
+            .iter()
+            .position(|existing| existing.intersect(rect).area() > 0. || *existing == rect)
+        {
+            rect = rect.union(self.0.swap_remove(idx));
+        }
+        self.0.push(rect);
+    }
+
+    /// Merges `other`'s rectangles into this region.
+    pub fn merge(&mut self, other: &Region) {
+        self.merge_translated(other, Vec2::ZERO);
+    }
+
+    /// Merges `other`'s rectangles into this region, translating each by
+    /// `offset` first.
+    ///
+    /// Used to bring a child widget's damage region, expressed in the
+    /// child's own local coordinates, into its parent's local coordinates
+    /// (the same translation `paint_with_offset` applies when painting).
+    pub fn merge_translated(&mut self, other: &Region, offset: Vec2) {
+        for rect in &other.0 {
+            self.add_rect(*rect + offset);
+        }
+    }
+
+    /// Adds a rect covering the entirety of a widget of the given `size`,
+    /// in that widget's own local coordinates (origin at zero).
+    pub fn add_whole_widget(&mut self, size: Size) {
+        self.add_rect(Rect::from_origin_size(Point::ORIGIN, size));
+    }
+
+    /// Returns the disjoint (well, coalesced) rectangles making up this
+    /// region.
+    pub fn rects(&self) -> &[Rect] {
+        &self.0
+    }
+}
+
+impl Default for Region {
+    fn default() -> Region {
+        Region::EMPTY
     }
 }
 
 impl From<Rect> for Region {
     fn from(src: Rect) -> Region {
-        Region(src)
+        if src.area() <= 0. {
+            Region::EMPTY
+        } else {
+            Region(vec![src])
+        }
     }
 }
 
@@ -639,6 +1155,7 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
             window_id: self.window_id,
             focus_widget: self.focus_widget,
             region: region.into(),
+            debug_logger: self.debug_logger.clone(),
         };
         f(&mut child_ctx)
     }
@@ -652,6 +1169,7 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
 pub struct LayoutCtx<'a, 'b: 'a> {
     pub(crate) text_factory: &'a mut Text<'b>,
     pub(crate) window_id: WindowId,
+    pub(crate) debug_logger: DebugLogger,
 }
 
 /// A mutable context provided to event handling methods of widgets.
@@ -667,6 +1185,12 @@ pub struct EventCtx<'a, 'b> {
     pub(crate) cursor: &'a mut Option<Cursor>,
     /// Commands submitted to be run after this event.
     pub(crate) command_queue: &'a mut CommandQueue,
+    /// Deferred mutations submitted to be run after this event, via
+    /// [`mutate_later`](#method.mutate_later).
+    pub(crate) mutate_queue: &'a mut MutateQueue,
+    /// Handle used by [`run_in_background`](#method.run_in_background) to
+    /// let background tasks post commands back into this window.
+    pub(crate) ext_event_sink: ExtEventSink,
     pub(crate) window_id: WindowId,
     // TODO: migrate most usage of `WindowHandle` to `WinCtx` instead.
     pub(crate) window: &'a WindowHandle,
@@ -675,6 +1199,19 @@ pub struct EventCtx<'a, 'b> {
     pub(crate) had_active: bool,
     pub(crate) is_handled: bool,
     pub(crate) is_root: bool,
+    /// Whether this widget or any of its ancestors is disabled, mirroring
+    /// the field of the same name on [`LifeCycleCtx`]/[`UpdateCtx`] -- kept
+    /// distinct from `base_state.is_disabled`, which only reflects this
+    /// widget's own flag.
+    ///
+    /// [`LifeCycleCtx`]: struct.LifeCycleCtx.html
+    /// [`UpdateCtx`]: struct.UpdateCtx.html
+    pub(crate) is_disabled: bool,
+    /// Handle used to record context activity for the [`DebugLogger`]
+    /// subsystem; a no-op unless logging has been enabled.
+    ///
+    /// [`DebugLogger`]: struct.DebugLogger.html
+    pub(crate) debug_logger: DebugLogger,
 }
 
 /// A mutable context provided to the [`lifecycle`] method on widgets.
@@ -698,8 +1235,13 @@ pub struct LifeCycleCtx<'a> {
     pub(crate) children_changed: bool,
     pub(crate) needs_inval: bool,
     pub(crate) request_anim: bool,
+    pub(crate) is_disabled: bool,
+    /// Handle used by [`spawn_worker`](#method.spawn_worker) to let
+    /// background tasks post commands back into this window.
+    pub(crate) ext_event_sink: ExtEventSink,
     pub(crate) window_id: WindowId,
     pub(crate) widget_id: WidgetId,
+    pub(crate) debug_logger: DebugLogger,
 }
 
 /// A mutable context provided to data update methods of widgets.
@@ -714,25 +1256,69 @@ pub struct UpdateCtx<'a, 'b: 'a> {
     // Discussion: we probably want to propagate more fine-grained
     // invalidations, which would mean a structure very much like
     // `EventCtx` (and possibly using the same structure). But for
-    // now keep it super-simple.
+    // now keep it super-simple. `EventCtx::invalidate_rect` covers the
+    // common case of a widget reacting to its own input; update passes
+    // still invalidate the whole widget until this gets the same
+    // treatment.
     pub(crate) needs_inval: bool,
     pub(crate) children_changed: bool,
+    pub(crate) is_disabled: bool,
     pub(crate) window_id: WindowId,
     pub(crate) widget_id: WidgetId,
+    pub(crate) debug_logger: DebugLogger,
 }
 
 impl<'a, 'b> EventCtx<'a, 'b> {
+    /// Record a [`DebugAction`] against this widget for the current pass.
+    ///
+    /// A no-op unless the [`DebugLogger`] threaded through this context has
+    /// been enabled.
+    ///
+    /// [`DebugAction`]: enum.DebugAction.html
+    /// [`DebugLogger`]: struct.DebugLogger.html
+    fn log(&self, action: DebugAction) {
+        self.debug_logger.record(
+            self.base_state.id,
+            DebugPass::Event,
+            action,
+            Some(self.size()),
+        );
+    }
+
     /// Invalidate.
     ///
-    /// Right now, it just invalidates the entire window, but we'll want
-    /// finer grained invalidation before long.
+    /// Marks this widget's entire area dirty. The damage is accumulated as a
+    /// [`Region`] on `BaseState` and merged up through ancestors. `paint_ctx`'s
+    /// [`Region`] already lets [`WidgetPod::paint_with_offset`] clip/skip
+    /// subtrees that don't overlap it; turning the *accumulated* damage from
+    /// this method into that paint region on the next frame, and clearing it
+    /// afterwards, is the job of the windowing/paint-scheduling code that
+    /// drives the event loop, which is outside this file.
+    ///
+    /// [`Region`]: struct.Region.html
+    /// [`WidgetPod::paint_with_offset`]: struct.WidgetPod.html#method.paint_with_offset
     pub fn invalidate(&mut self) {
-        // Note: for the current functionality, we could shortcut and just
-        // request an invalidate on the window. But when we do fine-grained
-        // invalidation, we'll want to compute the invalidation region, and
-        // that needs to be propagated (with, likely, special handling for
-        // scrolling).
-        self.base_state.needs_inval = true;
+        self.base_state.needs_inval.add_whole_widget(self.size());
+        self.log(DebugAction::Invalidate);
+    }
+
+    /// Invalidate a sub-rectangle of this widget, in its own local
+    /// coordinates, instead of its entire area.
+    ///
+    /// Use this instead of [`invalidate`] whenever only a known portion of
+    /// the widget actually changed, so the accumulated [`Region`] stays
+    /// precise instead of covering the widget's whole bounds. Like
+    /// [`invalidate`], the rect is accumulated into `BaseState` and
+    /// translated as it merges up through ancestors; turning that into an
+    /// actual repaint, and any widget-specific damage shape (e.g. a `Scroll`
+    /// only invalidating the band newly exposed by scrolling, translating
+    /// the rest), is left to the widgets and windowing code that consume it.
+    ///
+    /// [`invalidate`]: #method.invalidate
+    /// [`Region`]: struct.Region.html
+    pub fn invalidate_rect(&mut self, rect: Rect) {
+        self.base_state.needs_inval.add_rect(rect);
+        self.log(DebugAction::Invalidate);
     }
 
     /// Indicate that your children have changed.
@@ -740,6 +1326,7 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     /// Widgets must call this method after adding a new child.
     pub fn children_changed(&mut self) {
         self.base_state.children_changed = true;
+        self.log(DebugAction::ChildrenChanged);
     }
 
     /// Get an object which can create text layouts.
@@ -770,6 +1357,7 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     pub fn set_active(&mut self, active: bool) {
         self.base_state.is_active = active;
         // TODO: plumb mouse grab through to platform (through druid-shell)
+        self.log(DebugAction::SetActive(active));
     }
 
     /// The "hot" (aka hover) status of a widget.
@@ -804,6 +1392,42 @@ impl<'a, 'b> EventCtx<'a, 'b> {
         self.base_state.is_active
     }
 
+    /// Set the "disabled" state of the widget.
+    ///
+    /// A disabled widget (and its whole subtree) takes no part in mouse or
+    /// keyboard dispatch, and cannot become hot, active, or focused. If this
+    /// widget is currently active, disabling it revokes that status
+    /// immediately; if it or a descendant holds focus, disabling it queues a
+    /// `FocusChange::Resign` request, which takes effect when pending focus
+    /// changes are next resolved.
+    ///
+    /// See [`EventCtx::is_disabled`](struct.EventCtx.html#method.is_disabled).
+    pub fn set_disabled(&mut self, disabled: bool) {
+        if disabled {
+            self.base_state.is_active = false;
+            // Disabling a container whose subtree contains the focused leaf
+            // must resign focus too, not just disabling the leaf itself --
+            // `has_focus` (ancestor-inclusive), not `is_focused`.
+            if self.has_focus() {
+                self.base_state.request_focus = Some(FocusChange::Resign);
+            }
+        }
+        self.base_state.is_disabled = disabled;
+    }
+
+    /// The disabled state of a widget, including its ancestors.
+    ///
+    /// Returns `true` if this widget or any of its ancestors is disabled,
+    /// matching [`LifeCycleCtx::is_disabled`]/[`UpdateCtx::is_disabled`].
+    ///
+    /// See [`set_disabled`](struct.EventCtx.html#method.set_disabled).
+    ///
+    /// [`LifeCycleCtx::is_disabled`]: struct.LifeCycleCtx.html#method.is_disabled
+    /// [`UpdateCtx::is_disabled`]: struct.UpdateCtx.html#method.is_disabled
+    pub fn is_disabled(&self) -> bool {
+        self.is_disabled || self.base_state.is_disabled
+    }
+
     /// Returns a reference to the current `WindowHandle`.
     ///
     /// Note: we're in the process of migrating towards providing functionality
@@ -818,6 +1442,7 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     /// widgets.
     pub fn set_handled(&mut self) {
         self.is_handled = true;
+        self.log(DebugAction::SetHandled);
     }
 
     /// Determine whether the event has been handled by some other widget.
@@ -825,21 +1450,23 @@ impl<'a, 'b> EventCtx<'a, 'b> {
         self.is_handled
     }
 
-    /// The focus status of a widget.
+    /// The focus status of a widget, including its descendants.
     ///
-    /// Focus means that the widget receives keyboard events.
+    /// Returns `true` if this widget is the focused leaf, or an ancestor of
+    /// it. Container widgets that want to know whether *they themselves*
+    /// are the focused leaf, as opposed to merely containing it, should use
+    /// [`is_focused`] instead.
     ///
     /// A widget can request focus using the [`request_focus`] method.
     /// This will generally result in a separate event propagation of
-    /// a `FocusChanged` method, including sending `false` to the previous
-    /// widget that held focus.
-    ///
-    /// Only one leaf widget at a time has focus. However, in a container
-    /// hierarchy, all ancestors of that leaf widget are also invoked with
-    /// `FocusChanged(true)`.
+    /// a `FocusChanged` method, sending `true` to the newly focused leaf
+    /// and `false` to the previously focused one; ancestors whose subtree
+    /// gained or lost the focused widget are instead notified via
+    /// `ChildFocusChanged`.
     ///
-    /// Discussion question: is "is_focused" a better name?
+    /// Only one leaf widget at a time has focus.
     ///
+    /// [`is_focused`]: #method.is_focused
     /// [`request_focus`]: struct.EventCtx.html#method.request_focus
     pub fn has_focus(&self) -> bool {
         let is_child = self
@@ -849,6 +1476,19 @@ impl<'a, 'b> EventCtx<'a, 'b> {
         is_child || self.focus_widget == Some(self.widget_id())
     }
 
+    /// The focus status of this widget specifically.
+    ///
+    /// Returns `true` only if this exact widget is the focused leaf, as
+    /// opposed to [`has_focus`], which also returns `true` for its
+    /// ancestors. This is the right check for a widget (e.g. a `TextBox`)
+    /// that wants to change its own appearance when it is the one holding
+    /// keyboard focus.
+    ///
+    /// [`has_focus`]: #method.has_focus
+    pub fn is_focused(&self) -> bool {
+        self.focus_widget == Some(self.widget_id())
+    }
+
     /// Request keyboard focus.
     ///
     /// See [`has_focus`] for more information.
@@ -856,6 +1496,7 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     /// [`has_focus`]: struct.EventCtx.html#method.has_focus
     pub fn request_focus(&mut self) {
         self.base_state.request_focus = Some(FocusChange::Focus(self.widget_id()));
+        self.log(DebugAction::RequestFocus);
     }
 
     /// Transfer focus to the next focusable widget.
@@ -864,6 +1505,7 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     pub fn focus_next(&mut self) {
         if self.focus_widget == Some(self.widget_id()) {
             self.base_state.request_focus = Some(FocusChange::Next);
+            self.log(DebugAction::RequestFocus);
         } else {
             log::warn!("focus_next can only be called by the currently focused widget");
         }
@@ -875,6 +1517,7 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     pub fn focus_prev(&mut self) {
         if self.focus_widget == Some(self.widget_id()) {
             self.base_state.request_focus = Some(FocusChange::Previous);
+            self.log(DebugAction::RequestFocus);
         } else {
             log::warn!("focus_prev can only be called by the currently focused widget");
         }
@@ -886,6 +1529,7 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     pub fn resign_focus(&mut self) {
         if self.focus_widget == Some(self.widget_id()) {
             self.base_state.request_focus = Some(FocusChange::Resign);
+            self.log(DebugAction::RequestFocus);
         } else {
             log::warn!("resign_focus can only be called by the currently focused widget");
         }
@@ -894,7 +1538,8 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     /// Request an animation frame.
     pub fn request_anim_frame(&mut self) {
         self.base_state.request_anim = true;
-        self.base_state.needs_inval = true;
+        self.base_state.needs_inval.add_whole_widget(self.size());
+        self.log(DebugAction::RequestAnimFrame);
     }
 
     /// Request a timer event.
@@ -933,7 +1578,82 @@ impl<'a, 'b> EventCtx<'a, 'b> {
         target: impl Into<Option<Target>>,
     ) {
         let target = target.into().unwrap_or_else(|| self.window_id.into());
-        self.command_queue.push_back((target, command.into()))
+        self.command_queue.push_back((target, command.into()));
+        self.log(DebugAction::SubmitCommand);
+    }
+
+    /// Schedule a closure to run against another widget once this event has
+    /// finished propagating.
+    ///
+    /// Unlike [`submit_command`], which goes through `Data` and the normal
+    /// `update` diffing flow, this hands the closure direct mutable access to
+    /// the targeted widget, type-erased as `W`. This is useful for imperative
+    /// "touch that widget" changes -- for example restyling a sibling in
+    /// response to a click -- that don't fit naturally into `Data`.
+    ///
+    /// The closure is run once, after event handling completes, by a pass
+    /// that walks the tree to the widget with the given id. If no widget with
+    /// that id is found, or it is not of type `W`, the closure is dropped and
+    /// a warning is logged.
+    ///
+    /// [`submit_command`]: #method.submit_command
+    pub fn mutate_later<W: 'static>(&mut self, id: WidgetId, f: impl FnOnce(&mut W) + 'static) {
+        let callback: MutateCallback = Box::new(move |widget, _base_state| {
+            match widget.downcast_mut::<W>() {
+                Some(widget) => f(widget),
+                None => log::warn!(
+                    "mutate_later: widget {:?} was not of the expected type",
+                    id
+                ),
+            }
+        });
+        self.mutate_queue.push_back((id, callback));
+    }
+
+    /// Run a closure on a background thread, without blocking the event
+    /// loop.
+    ///
+    /// The closure is given an [`ExtEventSink`] it can use to submit
+    /// commands -- as many times as it likes, from whatever thread it ends
+    /// up running on -- which arrive back through the same
+    /// [`Event::TargetedCommand`] path as a regular [`submit_command`] call.
+    /// This is the way to do IO or long computation (loading an image,
+    /// running a query) without freezing paint and layout.
+    ///
+    /// [`ExtEventSink`]: struct.ExtEventSink.html
+    /// [`submit_command`]: #method.submit_command
+    pub fn run_in_background<F>(&mut self, task: F)
+    where
+        F: FnOnce(ExtEventSink) + Send + 'static,
+    {
+        let sink = self.ext_event_sink.clone();
+        std::thread::spawn(move || task(sink));
+    }
+
+    /// Run `task` on a background thread and deliver its result back to
+    /// this widget as a [`Command`], without blocking the event loop.
+    ///
+    /// This is a convenience over [`run_in_background`] for the common case
+    /// of "do some work, then hand the result to the widget that asked for
+    /// it": `task` is run on its own thread, and once it returns, `to_command`
+    /// turns the result into a `Command` that's submitted back to this
+    /// widget's id, arriving through the normal `command_queue` path between
+    /// event handling and `update`, same as any other submitted command.
+    ///
+    /// [`Command`]: struct.Command.html
+    /// [`run_in_background`]: #method.run_in_background
+    pub fn spawn_worker<R, F, C>(&mut self, task: F, to_command: C)
+    where
+        R: Send + 'static,
+        F: FnOnce() -> R + Send + 'static,
+        C: FnOnce(R) -> Command + Send + 'static,
+    {
+        let sink = self.ext_event_sink.clone();
+        let target = Target::Widget(self.widget_id());
+        std::thread::spawn(move || {
+            let result = task();
+            sink.submit_command(to_command(result), target);
+        });
     }
 
     /// Get the window id.
@@ -955,19 +1675,33 @@ impl<'a, 'b> EventCtx<'a, 'b> {
             children: Bloom::default(),
             focus_widgets: Vec::new(),
             request_anim: false,
+            is_disabled: self.is_disabled(),
+            ext_event_sink: self.ext_event_sink.clone(),
             window_id: self.window_id,
             widget_id,
+            debug_logger: self.debug_logger.clone(),
         }
     }
 }
 
 impl<'a> LifeCycleCtx<'a> {
+    /// Record a [`DebugAction`] against this widget for the current pass.
+    ///
+    /// See [`EventCtx::log`](struct.EventCtx.html) for more discussion.
+    ///
+    /// [`DebugAction`]: enum.DebugAction.html
+    fn log(&self, action: DebugAction) {
+        self.debug_logger
+            .record(self.widget_id, DebugPass::LifeCycle, action, None);
+    }
+
     /// Invalidate.
     ///
     /// See [`EventCtx::invalidate`](struct.EventCtx.html#method.invalidate) for
     /// more discussion.
     pub fn invalidate(&mut self) {
         self.needs_inval = true;
+        self.log(DebugAction::Invalidate);
     }
 
     /// Returns the current widget's `WidgetId`.
@@ -975,6 +1709,13 @@ impl<'a> LifeCycleCtx<'a> {
         self.widget_id
     }
 
+    /// The disabled state of a widget.
+    ///
+    /// See [`EventCtx::is_disabled`](struct.EventCtx.html#method.is_disabled).
+    pub fn is_disabled(&self) -> bool {
+        self.is_disabled
+    }
+
     /// Registers a child widget.
     ///
     /// This should only be called in response to a `LifeCycle::Register` event.
@@ -986,8 +1727,14 @@ impl<'a> LifeCycleCtx<'a> {
     }
 
     /// Register this widget to be eligile to accept focus automatically.
+    ///
+    /// Has no effect if the widget is currently disabled: a disabled widget
+    /// is excluded from the tab ring that `FocusChange::Next`/`Previous`
+    /// step through, so it's skipped rather than stopped on.
     pub fn register_for_focus(&mut self) {
-        self.focus_widgets.push(self.widget_id);
+        if !self.is_disabled {
+            self.focus_widgets.push(self.widget_id);
+        }
     }
 
     /// Indicate that your children have changed.
@@ -995,11 +1742,13 @@ impl<'a> LifeCycleCtx<'a> {
     /// Widgets must call this method after adding a new child.
     pub fn children_changed(&mut self) {
         self.children_changed = true;
+        self.log(DebugAction::ChildrenChanged);
     }
 
     /// Request an animation frame.
     pub fn request_anim_frame(&mut self) {
         self.request_anim = true;
+        self.log(DebugAction::RequestAnimFrame);
     }
 
     /// Submit a [`Command`] to be run after this event is handled.
@@ -1016,7 +1765,29 @@ impl<'a> LifeCycleCtx<'a> {
         target: impl Into<Option<Target>>,
     ) {
         let target = target.into().unwrap_or_else(|| self.window_id.into());
-        self.command_queue.push_back((target, command.into()))
+        self.command_queue.push_back((target, command.into()));
+        self.log(DebugAction::SubmitCommand);
+    }
+
+    /// Run `task` on a background thread and deliver its result back to this
+    /// widget as a [`Command`], without blocking the event loop.
+    ///
+    /// See [`EventCtx::spawn_worker`] for more discussion.
+    ///
+    /// [`Command`]: struct.Command.html
+    /// [`EventCtx::spawn_worker`]: struct.EventCtx.html#method.spawn_worker
+    pub fn spawn_worker<R, F, C>(&mut self, task: F, to_command: C)
+    where
+        R: Send + 'static,
+        F: FnOnce() -> R + Send + 'static,
+        C: FnOnce(R) -> Command + Send + 'static,
+    {
+        let sink = self.ext_event_sink.clone();
+        let target = Target::Widget(self.widget_id());
+        std::thread::spawn(move || {
+            let result = task();
+            sink.submit_command(to_command(result), target);
+        });
     }
 }
 
@@ -1033,12 +1804,23 @@ impl<'a, 'b> LayoutCtx<'a, 'b> {
 }
 
 impl<'a, 'b> UpdateCtx<'a, 'b> {
+    /// Record a [`DebugAction`] against this widget for the current pass.
+    ///
+    /// See [`EventCtx::log`](struct.EventCtx.html) for more discussion.
+    ///
+    /// [`DebugAction`]: enum.DebugAction.html
+    fn log(&self, action: DebugAction) {
+        self.debug_logger
+            .record(self.widget_id, DebugPass::Update, action, None);
+    }
+
     /// Invalidate.
     ///
     /// See [`EventCtx::invalidate`](struct.EventCtx.html#method.invalidate) for
     /// more discussion.
     pub fn invalidate(&mut self) {
         self.needs_inval = true;
+        self.log(DebugAction::Invalidate);
     }
 
     /// Indicate that your children have changed.
@@ -1046,6 +1828,14 @@ impl<'a, 'b> UpdateCtx<'a, 'b> {
     /// Widgets must call this method after adding a new child.
     pub fn children_changed(&mut self) {
         self.children_changed = true;
+        self.log(DebugAction::ChildrenChanged);
+    }
+
+    /// The disabled state of a widget.
+    ///
+    /// See [`EventCtx::is_disabled`](struct.EventCtx.html#method.is_disabled).
+    pub fn is_disabled(&self) -> bool {
+        self.is_disabled
     }
 
     /// Get an object which can create text layouts.
@@ -1106,9 +1896,12 @@ mod tests {
             children_changed: true,
             needs_inval: false,
             request_anim: false,
+            is_disabled: false,
+            ext_event_sink: ExtEventSink::new(WindowId::next()),
             window_id: WindowId::next(),
             widget_id: WidgetId::next(),
             focus_widgets: Vec::new(),
+            debug_logger: DebugLogger::new(),
         };
 
         let env = Env::default();
@@ -1119,4 +1912,230 @@ mod tests {
         assert!(ctx.children.contains(&id3));
         assert_eq!(ctx.children.entry_count(), 7);
     }
+
+    #[test]
+    fn child_focus_changed_routes_to_ancestor_via_children_bloom() {
+        // Regression test: `RouteFocusChanged` must consult the persisted
+        // `self.state.children` bloom built up by `LifeCycle::Register`, not
+        // the fresh per-call `ctx.children` accumulator (which is always
+        // empty outside of a `Register` pass). Getting this wrong makes
+        // `ChildFocusChanged` dead code for every ancestor.
+        let (id1, t1) = IdentityWrapper::wrap(TextBox::raw().parse());
+        let widget = Flex::row().with_child(t1, 1.0);
+        let mut outer = WidgetPod::new(widget).boxed();
+
+        let mut command_queue: CommandQueue = VecDeque::new();
+        let mut ctx = LifeCycleCtx {
+            command_queue: &mut command_queue,
+            children: Bloom::new(),
+            children_changed: true,
+            needs_inval: false,
+            request_anim: false,
+            is_disabled: false,
+            ext_event_sink: ExtEventSink::new(WindowId::next()),
+            window_id: WindowId::next(),
+            widget_id: WidgetId::next(),
+            focus_widgets: Vec::new(),
+            debug_logger: DebugLogger::new(),
+        };
+        let env = Env::default();
+
+        outer.lifecycle(&mut ctx, &LifeCycle::Register, &None, &env);
+        assert!(outer.state.children.contains(&id1));
+
+        outer.lifecycle(
+            &mut ctx,
+            &LifeCycle::RouteFocusChanged {
+                old: None,
+                new: Some(id1),
+            },
+            &None,
+            &env,
+        );
+        assert!(outer.state.has_focus);
+
+        outer.lifecycle(
+            &mut ctx,
+            &LifeCycle::RouteFocusChanged {
+                old: Some(id1),
+                new: None,
+            },
+            &None,
+            &env,
+        );
+        assert!(!outer.state.has_focus);
+    }
+
+    #[test]
+    fn disabled_ancestor_excludes_descendants_from_focus_chain() {
+        let (id, text_box) = IdentityWrapper::wrap(TextBox::raw().parse());
+        let container = Flex::row().with_child(text_box, 1.0);
+        let mut outer = WidgetPod::new(container).boxed();
+        outer.state.is_disabled = true;
+
+        let mut command_queue: CommandQueue = VecDeque::new();
+        let mut ctx = LifeCycleCtx {
+            command_queue: &mut command_queue,
+            children: Bloom::new(),
+            children_changed: true,
+            needs_inval: false,
+            request_anim: false,
+            is_disabled: false,
+            ext_event_sink: ExtEventSink::new(WindowId::next()),
+            window_id: WindowId::next(),
+            widget_id: WidgetId::next(),
+            focus_widgets: Vec::new(),
+            debug_logger: DebugLogger::new(),
+        };
+
+        let env = Env::default();
+
+        outer.lifecycle(&mut ctx, &LifeCycle::Register, &None, &env);
+        assert!(!ctx.focus_widgets.contains(&id));
+    }
+
+    #[test]
+    fn mutate_later_reaches_a_boxed_child_widget() {
+        // Regression test: `WidgetPod::mutate` must downcast to the concrete
+        // widget, not to `W` itself -- for a `BoxedWidget<T>` (`W = Box<dyn
+        // Widget<T>>`), `&mut self.inner` coerced straight to `&mut dyn Any`
+        // would make the box the `Any` type, so `mutate_later`'s
+        // `downcast_mut::<TextBox>()` could never succeed.
+        let mut boxed: BoxedWidget<Option<u32>> = WidgetPod::new(TextBox::raw().parse()).boxed();
+        let id = boxed.id();
+
+        let ran = Rc::new(RefCell::new(false));
+        let ran2 = ran.clone();
+        let callback: MutateCallback = Box::new(move |widget, _base_state| {
+            match widget.downcast_mut::<TextBox>() {
+                Some(_) => *ran2.borrow_mut() = true,
+                None => panic!("mutate_later did not reach the concrete widget"),
+            }
+        });
+
+        assert!(boxed.mutate(id, callback));
+        assert!(*ran.borrow());
+    }
+
+    #[test]
+    fn resolve_focus_change_wraps_around() {
+        let mut state = BaseState::new(WidgetId::next());
+        let ids: Vec<WidgetId> = (0..3).map(|_| WidgetId::next()).collect();
+        state.focus_chain = ids.clone();
+
+        assert_eq!(
+            state.resolve_focus_change(None, FocusChange::Next),
+            Some(ids[0])
+        );
+        assert_eq!(
+            state.resolve_focus_change(None, FocusChange::Previous),
+            Some(ids[2])
+        );
+        assert_eq!(
+            state.resolve_focus_change(Some(ids[0]), FocusChange::Next),
+            Some(ids[1])
+        );
+        assert_eq!(
+            state.resolve_focus_change(Some(ids[2]), FocusChange::Next),
+            Some(ids[0])
+        );
+        assert_eq!(
+            state.resolve_focus_change(Some(ids[0]), FocusChange::Previous),
+            Some(ids[2])
+        );
+    }
+
+    #[test]
+    fn resolve_focus_change_empty_chain() {
+        let state = BaseState::new(WidgetId::next());
+        assert_eq!(state.resolve_focus_change(None, FocusChange::Next), None);
+    }
+
+    #[test]
+    fn resolve_pending_focus_next_and_resign() {
+        let mut state = BaseState::new(WidgetId::next());
+        let ids: Vec<WidgetId> = (0..2).map(|_| WidgetId::next()).collect();
+        state.focus_chain = ids.clone();
+
+        state.request_focus = Some(FocusChange::Next);
+        assert_eq!(
+            state.resolve_pending_focus(Some(ids[0])),
+            Some((Some(ids[0]), Some(ids[1])))
+        );
+        // the request was consumed
+        assert_eq!(state.resolve_pending_focus(Some(ids[0])), None);
+
+        state.request_focus = Some(FocusChange::Resign);
+        assert_eq!(
+            state.resolve_pending_focus(Some(ids[1])),
+            Some((Some(ids[1]), None))
+        );
+    }
+
+    #[test]
+    fn resolve_pending_focus_next_with_empty_chain_resigns() {
+        let mut state = BaseState::new(WidgetId::next());
+        let current = WidgetId::next();
+        state.request_focus = Some(FocusChange::Next);
+        assert_eq!(
+            state.resolve_pending_focus(Some(current)),
+            Some((Some(current), None))
+        );
+    }
+
+    #[test]
+    fn region_coalesces_overlapping_rects() {
+        let mut region = Region::EMPTY;
+        region.add_rect(Rect::new(0., 0., 10., 10.));
+        region.add_rect(Rect::new(5., 5., 15., 15.));
+        assert_eq!(region.rects().len(), 1);
+        assert_eq!(region.to_rect(), Rect::new(0., 0., 15., 15.));
+
+        region.add_rect(Rect::new(100., 100., 110., 110.));
+        assert_eq!(region.rects().len(), 2);
+        assert!(region.intersects(Rect::new(8., 8., 9., 9.)));
+        assert!(region.intersects(Rect::new(101., 101., 102., 102.)));
+        assert!(!region.intersects(Rect::new(50., 50., 60., 60.)));
+    }
+
+    #[test]
+    fn region_merge_translated() {
+        let mut parent = Region::EMPTY;
+        let mut child = Region::EMPTY;
+        child.add_rect(Rect::new(0., 0., 5., 5.));
+        parent.merge_translated(&child, Vec2::new(10., 20.));
+        assert_eq!(parent.to_rect(), Rect::new(10., 20., 15., 25.));
+    }
+
+    #[test]
+    fn debug_logger_records_only_when_enabled() {
+        let logger = DebugLogger::new();
+        let id = WidgetId::next();
+        logger.record(id, DebugPass::Event, DebugAction::Invalidate, None);
+        assert!(logger.entries().is_empty());
+
+        logger.set_enabled(true);
+        logger.record(id, DebugPass::Event, DebugAction::Invalidate, None);
+        let entries = logger.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].widget_id, id);
+        assert_eq!(entries[0].pass, DebugPass::Event);
+
+        logger.clear();
+        assert!(logger.entries().is_empty());
+    }
+
+    #[test]
+    fn debug_logger_clones_share_the_log() {
+        let logger = DebugLogger::new();
+        logger.set_enabled(true);
+        let clone = logger.clone();
+        clone.record(
+            WidgetId::next(),
+            DebugPass::Update,
+            DebugAction::SetHandled,
+            None,
+        );
+        assert_eq!(logger.entries().len(), 1);
+    }
 }